@@ -5,6 +5,7 @@
 use crate::backend::utils;
 use crate::error::{CryptographyError, CryptographyResult};
 use crate::{exceptions, types};
+use foreign_types::ForeignType;
 
 #[pyo3::prelude::pyclass(
     frozen,
@@ -55,6 +56,13 @@ fn generate_parameters(key_size: u32) -> CryptographyResult<DsaParameters> {
     Ok(DsaParameters { dsa })
 }
 
+#[pyo3::prelude::pyfunction]
+fn generate_private_key(key_size: u32) -> CryptographyResult<DsaPrivateKey> {
+    let dsa = openssl::dsa::Dsa::generate_params(key_size)?.generate_key()?;
+    let pkey = openssl::pkey::PKey::from_dsa(dsa)?;
+    Ok(DsaPrivateKey { pkey })
+}
+
 #[pyo3::prelude::pyfunction]
 fn from_private_numbers(
     py: pyo3::Python<'_>,
@@ -113,15 +121,324 @@ fn clone_dsa_params<T: openssl::pkey::HasParams>(
     openssl::dsa::Dsa::from_pqg(d.p().to_owned()?, d.q().to_owned()?, d.g().to_owned()?)
 }
 
+// `openssl`'s safe `Dsa<T>` wrapper only exposes (de)serialization for keys
+// (via `PKey`), and `openssl-sys` itself has no binding for a bare
+// `Dss-Parms` (p, q, g) triple (unlike DH, which exposes
+// `i2d_DHparams`/`d2i_DHparams`). `Dss-Parms` is just
+// `SEQUENCE { p INTEGER, q INTEGER, g INTEGER }`, so it's hand-encoded here
+// instead of going through a nonexistent C binding.
+fn der_push_length(out: &mut Vec<u8>, len: usize) {
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let len_bytes = len.to_be_bytes();
+        let significant = &len_bytes[len_bytes
+            .iter()
+            .position(|&b| b != 0)
+            .unwrap_or(len_bytes.len() - 1)..];
+        out.push(0x80 | significant.len() as u8);
+        out.extend_from_slice(significant);
+    }
+}
+
+fn der_encode_uint(n: &openssl::bn::BigNumRef) -> Vec<u8> {
+    let mut bytes = n.to_vec();
+    if bytes.is_empty() {
+        bytes.push(0);
+    } else if bytes[0] & 0x80 != 0 {
+        bytes.insert(0, 0);
+    }
+    let mut out = vec![0x02u8];
+    der_push_length(&mut out, bytes.len());
+    out.extend_from_slice(&bytes);
+    out
+}
+
+fn invalid_dsa_params_der() -> CryptographyError {
+    CryptographyError::from(pyo3::exceptions::PyValueError::new_err(
+        "Unable to load DER-encoded DSA parameters.",
+    ))
+}
+
+fn der_read_tlv(data: &[u8], pos: usize, expected_tag: u8) -> CryptographyResult<(&[u8], usize)> {
+    if pos >= data.len() || data[pos] != expected_tag {
+        return Err(invalid_dsa_params_der());
+    }
+    let mut idx = pos + 1;
+    let first_len_byte = *data.get(idx).ok_or_else(invalid_dsa_params_der)?;
+    idx += 1;
+    let len = if first_len_byte & 0x80 == 0 {
+        first_len_byte as usize
+    } else {
+        let num_len_bytes = (first_len_byte & 0x7f) as usize;
+        let len_bytes = data
+            .get(idx..idx + num_len_bytes)
+            .ok_or_else(invalid_dsa_params_der)?;
+        idx += num_len_bytes;
+        len_bytes
+            .iter()
+            .fold(0usize, |acc, &b| (acc << 8) | b as usize)
+    };
+    let content = data
+        .get(idx..idx + len)
+        .ok_or_else(invalid_dsa_params_der)?;
+    Ok((content, idx + len))
+}
+
+fn dsa_params_to_der(
+    dsa: &openssl::dsa::DsaRef<openssl::pkey::Params>,
+) -> CryptographyResult<Vec<u8>> {
+    let mut body = der_encode_uint(dsa.p());
+    body.extend(der_encode_uint(dsa.q()));
+    body.extend(der_encode_uint(dsa.g()));
+
+    let mut out = vec![0x30u8];
+    der_push_length(&mut out, body.len());
+    out.extend(body);
+    Ok(out)
+}
+
+fn der_read_uint(data: &[u8], pos: usize) -> CryptographyResult<(&[u8], usize)> {
+    let (content, next_pos) = der_read_tlv(data, pos, 0x02)?;
+    // Dss-Parms' p, q, g are always non-negative, so reject the DER INTEGER
+    // encodings `der_encode_uint` never produces: empty content, and a
+    // high-bit-set leading byte (two's-complement negative) without the
+    // `0x00` padding byte that keeps it positive.
+    match content {
+        [] => Err(invalid_dsa_params_der()),
+        [first, ..] if first & 0x80 != 0 => Err(invalid_dsa_params_der()),
+        _ => Ok((content, next_pos)),
+    }
+}
+
+fn dsa_params_from_der_bytes(
+    data: &[u8],
+) -> CryptographyResult<openssl::dsa::Dsa<openssl::pkey::Params>> {
+    let (seq, seq_end) = der_read_tlv(data, 0, 0x30)?;
+    if seq_end != data.len() {
+        return Err(invalid_dsa_params_der());
+    }
+    let (p_bytes, pos) = der_read_uint(seq, 0)?;
+    let (q_bytes, pos) = der_read_uint(seq, pos)?;
+    let (g_bytes, pos) = der_read_uint(seq, pos)?;
+    if pos != seq.len() {
+        return Err(invalid_dsa_params_der());
+    }
+
+    let p = openssl::bn::BigNum::from_slice(p_bytes)?;
+    let q = openssl::bn::BigNum::from_slice(q_bytes)?;
+    let g = openssl::bn::BigNum::from_slice(g_bytes)?;
+    Ok(openssl::dsa::Dsa::from_pqg(p, q, g).unwrap())
+}
+
+#[pyo3::prelude::pyfunction]
+fn parameters_from_der(data: &[u8]) -> CryptographyResult<DsaParameters> {
+    let dsa = dsa_params_from_der_bytes(data)?;
+    Ok(DsaParameters { dsa })
+}
+
+#[pyo3::prelude::pyfunction]
+fn parameters_from_pem(data: &[u8]) -> CryptographyResult<DsaParameters> {
+    let parsed = pem::parse(data).map_err(|_| {
+        CryptographyError::from(pyo3::exceptions::PyValueError::new_err(
+            "Unable to load PEM-encoded DSA parameters.",
+        ))
+    })?;
+    let dsa = dsa_params_from_der_bytes(parsed.contents())?;
+    Ok(DsaParameters { dsa })
+}
+
+fn int2octets(x: &openssl::bn::BigNumRef, rolen: usize) -> Vec<u8> {
+    let bytes = x.to_vec();
+    match bytes.len().cmp(&rolen) {
+        std::cmp::Ordering::Less => {
+            let mut padded = vec![0u8; rolen - bytes.len()];
+            padded.extend_from_slice(&bytes);
+            padded
+        }
+        std::cmp::Ordering::Greater => bytes[bytes.len() - rolen..].to_vec(),
+        std::cmp::Ordering::Equal => bytes,
+    }
+}
+
+fn bits2int(data: &[u8], qlen: usize) -> CryptographyResult<openssl::bn::BigNum> {
+    let v = openssl::bn::BigNum::from_slice(data)?;
+    let vlen = data.len() * 8;
+    if vlen > qlen {
+        let mut shifted = openssl::bn::BigNum::new()?;
+        shifted.rshift(&v, (vlen - qlen) as i32)?;
+        Ok(shifted)
+    } else {
+        Ok(v)
+    }
+}
+
+fn bits2octets(
+    data: &[u8],
+    q: &openssl::bn::BigNumRef,
+    qlen: usize,
+    rolen: usize,
+) -> CryptographyResult<Vec<u8>> {
+    let mut ctx = openssl::bn::BigNumContext::new()?;
+    let z1 = bits2int(data, qlen)?;
+    let mut z2 = openssl::bn::BigNum::new()?;
+    z2.nnmod(&z1, q, &mut ctx)?;
+    Ok(int2octets(&z2, rolen))
+}
+
+fn hmac(
+    key: &[u8],
+    chunks: &[&[u8]],
+    digest: openssl::hash::MessageDigest,
+) -> CryptographyResult<Vec<u8>> {
+    let pkey = openssl::pkey::PKey::hmac(key)?;
+    let mut signer = openssl::sign::Signer::new(digest, &pkey)?;
+    for chunk in chunks {
+        signer.update(chunk)?;
+    }
+    Ok(signer.sign_to_vec()?)
+}
+
+fn dsa_sign_with_k(
+    dsa: &openssl::dsa::DsaRef<openssl::pkey::Private>,
+    k: &openssl::bn::BigNumRef,
+    h1: &[u8],
+) -> CryptographyResult<Option<(openssl::bn::BigNum, openssl::bn::BigNum)>> {
+    let mut ctx = openssl::bn::BigNumContext::new()?;
+    let p = dsa.p();
+    let q = dsa.q();
+    let g = dsa.g();
+    let x = dsa.priv_key();
+
+    let mut r_full = openssl::bn::BigNum::new()?;
+    r_full.mod_exp(g, k, p, &mut ctx)?;
+    let mut r = openssl::bn::BigNum::new()?;
+    r.nnmod(&r_full, q, &mut ctx)?;
+    let zero = openssl::bn::BigNum::from_u32(0)?;
+    if r == zero {
+        return Ok(None);
+    }
+
+    let z = bits2int(h1, q.num_bits() as usize)?;
+
+    let mut k_inv = openssl::bn::BigNum::new()?;
+    k_inv.mod_inverse(k, q, &mut ctx)?;
+
+    let mut xr = openssl::bn::BigNum::new()?;
+    xr.mod_mul(x, &r, q, &mut ctx)?;
+
+    let mut z_plus_xr = openssl::bn::BigNum::new()?;
+    z_plus_xr.checked_add(&z, &xr)?;
+
+    let mut s = openssl::bn::BigNum::new()?;
+    s.mod_mul(&k_inv, &z_plus_xr, q, &mut ctx)?;
+    if s == zero {
+        return Ok(None);
+    }
+
+    Ok(Some((r, s)))
+}
+
+fn encode_dsa_signature(
+    r: openssl::bn::BigNum,
+    s: openssl::bn::BigNum,
+) -> CryptographyResult<Vec<u8>> {
+    unsafe {
+        let sig = openssl_sys::DSA_SIG_new();
+        if sig.is_null() {
+            return Err(CryptographyError::from(openssl::error::ErrorStack::get()));
+        }
+        let rc = openssl_sys::DSA_SIG_set0(sig, r.as_ptr(), s.as_ptr());
+        if rc == 0 {
+            openssl_sys::DSA_SIG_free(sig);
+            return Err(CryptographyError::from(openssl::error::ErrorStack::get()));
+        }
+        // `DSA_SIG_set0` takes ownership of `r` and `s`'s underlying BIGNUMs.
+        std::mem::forget(r);
+        std::mem::forget(s);
+
+        let len = openssl_sys::i2d_DSA_SIG(sig, std::ptr::null_mut());
+        if len < 0 {
+            openssl_sys::DSA_SIG_free(sig);
+            return Err(CryptographyError::from(openssl::error::ErrorStack::get()));
+        }
+        let mut buf = vec![0u8; len as usize];
+        let mut buf_ptr = buf.as_mut_ptr();
+        let written = openssl_sys::i2d_DSA_SIG(sig, &mut buf_ptr);
+        openssl_sys::DSA_SIG_free(sig);
+        if written < 0 {
+            return Err(CryptographyError::from(openssl::error::ErrorStack::get()));
+        }
+        buf.truncate(written as usize);
+        Ok(buf)
+    }
+}
+
+// RFC 6979 section 3.2: derive the per-signature nonce `k` solely from the
+// private key and message digest via an HMAC-DRBG, so signing no longer
+// depends on the quality (or even presence) of a system RNG.
+fn rfc6979_sign(
+    dsa: &openssl::dsa::DsaRef<openssl::pkey::Private>,
+    h1: &[u8],
+    digest: openssl::hash::MessageDigest,
+) -> CryptographyResult<Vec<u8>> {
+    let q = dsa.q();
+    let x = dsa.priv_key();
+    let hlen = digest.size();
+    let qlen = q.num_bits() as usize;
+    let rolen = qlen.div_ceil(8);
+
+    let x_octets = int2octets(x, rolen);
+    let h1_octets = bits2octets(h1, q, qlen, rolen)?;
+
+    let mut v = vec![0x01u8; hlen];
+    let mut k = vec![0x00u8; hlen];
+
+    k = hmac(&k, &[&v, &[0x00], &x_octets, &h1_octets], digest)?;
+    v = hmac(&k, &[&v], digest)?;
+    k = hmac(&k, &[&v, &[0x01], &x_octets, &h1_octets], digest)?;
+    v = hmac(&k, &[&v], digest)?;
+
+    let one = openssl::bn::BigNum::from_u32(1)?;
+    let mut q_minus_1 = openssl::bn::BigNum::new()?;
+    q_minus_1.checked_sub(q, &one)?;
+
+    loop {
+        let mut t = Vec::new();
+        while t.len() < rolen {
+            v = hmac(&k, &[&v], digest)?;
+            t.extend_from_slice(&v);
+        }
+
+        let candidate_k = bits2int(&t, qlen)?;
+        if candidate_k >= one && candidate_k <= q_minus_1 {
+            if let Some((r, s)) = dsa_sign_with_k(dsa, &candidate_k, h1)? {
+                return encode_dsa_signature(r, s);
+            }
+        }
+
+        k = hmac(&k, &[&v, &[0x00]], digest)?;
+        v = hmac(&k, &[&v], digest)?;
+    }
+}
+
 #[pyo3::prelude::pymethods]
 impl DsaPrivateKey {
+    #[pyo3(signature = (data, algorithm, deterministic = false))]
     fn sign<'p>(
         &self,
         py: pyo3::Python<'p>,
         data: &[u8],
         algorithm: &pyo3::PyAny,
+        deterministic: bool,
     ) -> CryptographyResult<&'p pyo3::types::PyBytes> {
-        let (data, _) = utils::calculate_digest_and_algorithm(py, data, algorithm)?;
+        let (data, digest) = utils::calculate_digest_and_algorithm(py, data, algorithm)?;
+
+        if deterministic {
+            let dsa = self.pkey.dsa()?;
+            let sig = rfc6979_sign(&dsa, data, digest)?;
+            return Ok(pyo3::types::PyBytes::new(py, &sig));
+        }
 
         let mut signer = openssl::pkey_ctx::PkeyCtx::new(&self.pkey)?;
         signer.sign_init()?;
@@ -280,14 +597,213 @@ impl DsaParameters {
             .get(py)?
             .call1((py_p, py_q, py_g))?)
     }
+
+    // FIPS 186-4 A.1.1.1 / A.2.2: q and p must be prime, p - 1 must be a
+    // multiple of q, and g must generate the order-q subgroup of Z*_p.
+    fn validate(&self) -> CryptographyResult<bool> {
+        let mut ctx = openssl::bn::BigNumContext::new()?;
+        let p = self.dsa.p();
+        let q = self.dsa.q();
+        let g = self.dsa.g();
+
+        if !q.is_prime(64, &mut ctx)? || !p.is_prime(64, &mut ctx)? {
+            return Ok(false);
+        }
+
+        let one = openssl::bn::BigNum::from_u32(1)?;
+        let mut p_minus_1 = openssl::bn::BigNum::new()?;
+        p_minus_1.checked_sub(p, &one)?;
+
+        let mut remainder = openssl::bn::BigNum::new()?;
+        remainder.nnmod(&p_minus_1, q, &mut ctx)?;
+        let zero = openssl::bn::BigNum::from_u32(0)?;
+        if remainder != zero {
+            return Ok(false);
+        }
+
+        if g <= &one || g > &p_minus_1 {
+            return Ok(false);
+        }
+
+        let mut g_to_q = openssl::bn::BigNum::new()?;
+        g_to_q.mod_exp(g, q, p, &mut ctx)?;
+
+        Ok(g_to_q == one)
+    }
+
+    fn parameter_bytes<'p>(
+        &self,
+        py: pyo3::Python<'p>,
+        encoding: &pyo3::PyAny,
+        format: &pyo3::PyAny,
+    ) -> CryptographyResult<&'p pyo3::types::PyBytes> {
+        if !format.is(types::PARAMETER_FORMAT_PKCS3.get(py)?) {
+            return Err(CryptographyError::from(
+                pyo3::exceptions::PyValueError::new_err(
+                    "DSA parameters only support PKCS3 serialization format.",
+                ),
+            ));
+        }
+
+        let der_bytes = dsa_params_to_der(&self.dsa)?;
+        if encoding.is(types::ENCODING_DER.get(py)?) {
+            Ok(pyo3::types::PyBytes::new(py, &der_bytes))
+        } else if encoding.is(types::ENCODING_PEM.get(py)?) {
+            let pem_bytes = pem::encode(&pem::Pem::new("DSA PARAMETERS", der_bytes));
+            Ok(pyo3::types::PyBytes::new(py, pem_bytes.as_bytes()))
+        } else {
+            Err(CryptographyError::from(
+                pyo3::exceptions::PyValueError::new_err(
+                    "DSA parameters must be encoded with either PEM or DER encoding.",
+                ),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn der_round_trips_through_encode_and_decode() {
+        let dsa = openssl::dsa::Dsa::from_pqg(
+            // High bit set, so `der_encode_uint` must insert a leading
+            // `0x00` to keep this a positive DER INTEGER.
+            openssl::bn::BigNum::from_u32(0xff).unwrap(),
+            openssl::bn::BigNum::from_u32(17).unwrap(),
+            openssl::bn::BigNum::from_u32(3).unwrap(),
+        )
+        .unwrap();
+
+        let der = dsa_params_to_der(&dsa).unwrap();
+        let parsed = dsa_params_from_der_bytes(&der).unwrap();
+
+        assert_eq!(parsed.p().to_owned().unwrap(), dsa.p().to_owned().unwrap());
+        assert_eq!(parsed.q().to_owned().unwrap(), dsa.q().to_owned().unwrap());
+        assert_eq!(parsed.g().to_owned().unwrap(), dsa.g().to_owned().unwrap());
+    }
+
+    #[test]
+    fn der_rejects_negative_integer_without_padding() {
+        // SEQUENCE { INTEGER 0xff, INTEGER 1, INTEGER 1 }, where the first
+        // INTEGER's content is a bare 0xff byte instead of `0x00 0xff` -
+        // that's a negative number in DER, which `der_encode_uint` never
+        // produces and `der_read_uint` must reject.
+        let malformed = [
+            0x30, 0x09, 0x02, 0x01, 0xff, 0x02, 0x01, 0x01, 0x02, 0x01, 0x01,
+        ];
+        assert!(dsa_params_from_der_bytes(&malformed).is_err());
+    }
+
+    #[test]
+    fn der_rejects_trailing_garbage() {
+        let dsa = openssl::dsa::Dsa::from_pqg(
+            openssl::bn::BigNum::from_u32(23).unwrap(),
+            openssl::bn::BigNum::from_u32(11).unwrap(),
+            openssl::bn::BigNum::from_u32(4).unwrap(),
+        )
+        .unwrap();
+        let mut der = dsa_params_to_der(&dsa).unwrap();
+        der.push(0x00);
+        assert!(dsa_params_from_der_bytes(&der).is_err());
+    }
+
+    #[test]
+    fn validate_accepts_generated_parameters() {
+        let dsa = openssl::dsa::Dsa::generate_params(1024).unwrap();
+        let params = DsaParameters { dsa };
+        assert!(params.validate().unwrap());
+    }
+
+    #[test]
+    fn validate_rejects_generator_outside_the_order_q_subgroup() {
+        // g = p - 1 passes the `2 <= g <= p - 1` range check, but since q is
+        // odd, (p - 1)^q mod p = p - 1 != 1, so only the subgroup check
+        // rejects it.
+        let dsa = openssl::dsa::Dsa::generate_params(1024).unwrap();
+        let mut p_minus_1 = openssl::bn::BigNum::new().unwrap();
+        p_minus_1
+            .checked_sub(dsa.p(), &openssl::bn::BigNum::from_u32(1).unwrap())
+            .unwrap();
+        let tampered = openssl::dsa::Dsa::from_pqg(
+            dsa.p().to_owned().unwrap(),
+            dsa.q().to_owned().unwrap(),
+            p_minus_1,
+        )
+        .unwrap();
+        let params = DsaParameters { dsa: tampered };
+        assert!(!params.validate().unwrap());
+    }
+
+    #[test]
+    fn validate_rejects_q_that_does_not_divide_p_minus_1() {
+        // `q` from an unrelated, independently generated parameter set is
+        // still prime, so this isolates the "p - 1 must be a multiple of
+        // q" check from the primality checks above it.
+        let dsa = openssl::dsa::Dsa::generate_params(1024).unwrap();
+        let other = openssl::dsa::Dsa::generate_params(1024).unwrap();
+        let tampered = openssl::dsa::Dsa::from_pqg(
+            dsa.p().to_owned().unwrap(),
+            other.q().to_owned().unwrap(),
+            dsa.g().to_owned().unwrap(),
+        )
+        .unwrap();
+        let params = DsaParameters { dsa: tampered };
+        assert!(!params.validate().unwrap());
+    }
+
+    #[test]
+    fn int2octets_pads_and_truncates() {
+        let three = openssl::bn::BigNum::from_u32(3).unwrap();
+        assert_eq!(int2octets(&three, 4), vec![0, 0, 0, 3]);
+
+        let five_bytes = openssl::bn::BigNum::from_slice(&[1, 2, 3, 4, 5]).unwrap();
+        assert_eq!(int2octets(&five_bytes, 3), vec![3, 4, 5]);
+    }
+
+    #[test]
+    fn rfc6979_sign_is_deterministic_and_verifies() {
+        let dsa = openssl::dsa::Dsa::generate_params(1024)
+            .unwrap()
+            .generate_key()
+            .unwrap();
+        let digest = openssl::hash::MessageDigest::sha256();
+        let h1 = openssl::sha::sha256(b"sample");
+
+        let sig1 = rfc6979_sign(&dsa, &h1, digest).unwrap();
+        let sig2 = rfc6979_sign(&dsa, &h1, digest).unwrap();
+        assert_eq!(sig1, sig2, "same key and message must yield the same nonce");
+
+        let pkey = openssl::pkey::PKey::from_dsa(dsa).unwrap();
+        let mut verifier = openssl::pkey_ctx::PkeyCtx::new(&pkey).unwrap();
+        verifier.verify_init().unwrap();
+        assert!(verifier.verify(&h1, &sig1).unwrap());
+    }
+
+    #[test]
+    fn rfc6979_sign_differs_across_messages() {
+        let dsa = openssl::dsa::Dsa::generate_params(1024)
+            .unwrap()
+            .generate_key()
+            .unwrap();
+        let digest = openssl::hash::MessageDigest::sha256();
+
+        let sig1 = rfc6979_sign(&dsa, &openssl::sha::sha256(b"sample"), digest).unwrap();
+        let sig2 = rfc6979_sign(&dsa, &openssl::sha::sha256(b"test"), digest).unwrap();
+        assert_ne!(sig1, sig2);
+    }
 }
 
 pub(crate) fn create_module(py: pyo3::Python<'_>) -> pyo3::PyResult<&pyo3::prelude::PyModule> {
     let m = pyo3::prelude::PyModule::new(py, "dsa")?;
     m.add_function(pyo3::wrap_pyfunction!(generate_parameters, m)?)?;
+    m.add_function(pyo3::wrap_pyfunction!(generate_private_key, m)?)?;
     m.add_function(pyo3::wrap_pyfunction!(from_private_numbers, m)?)?;
     m.add_function(pyo3::wrap_pyfunction!(from_public_numbers, m)?)?;
     m.add_function(pyo3::wrap_pyfunction!(from_parameter_numbers, m)?)?;
+    m.add_function(pyo3::wrap_pyfunction!(parameters_from_der, m)?)?;
+    m.add_function(pyo3::wrap_pyfunction!(parameters_from_pem, m)?)?;
 
     m.add_class::<DsaPrivateKey>()?;
     m.add_class::<DsaPublicKey>()?;